@@ -5,16 +5,23 @@
 /// 
 /// For each sample, call [squine.update()][Squinewave::update] or the various setters (set_freq, set_clip, set_skew, set_sync),
 /// then call [squine.generate()][Squinewave::generate] to generate one sample.  
-/// After this, the getters [squine.audio()][Squinewave::audio] and [squine.sync()][Squinewave::sync] are available to use in your synth.  
+/// After this, the getters [squine.audio()][Squinewave::audio] and [squine.sync()][Squinewave::sync] are available to use in your synth.
+///
+/// For buffer-oriented hosts, `Squinewave` also implements `Iterator<Item = f64>`, and
+/// [generate_block()][Squinewave::generate_block] / [generate_block_sync()][Squinewave::generate_block_sync] fill whole slices.
 pub struct Squinewave {
 	// Signal inputs, set per sample (before generate() call)
 	freq: f64,
 	clip: f64,
 	skew: f64,
 	sync_in: bool,
-	// Through-Zero detection	
+	// Through-Zero detection
 	raw_freq: f64,
-	neg_freq: bool, 
+	neg_freq: bool,
+	// Un-transformed clip/skew inputs, kept so LFOs (see lfos below) can modulate around them
+	// without drifting; raw_freq above already serves this role for freq.
+	base_clip: f64,
+	base_skew: f64,
 
 	// Outputs
 	audio_out: f64,
@@ -27,6 +34,18 @@ pub struct Squinewave {
 	hardsync_phase: f64,
 	hardsync_inc: f64,
 
+	// Optional per-note amplitude envelope, see note_on()/note_off()
+	envelope: Option<Envelope>,
+
+	// In-progress frequency ramp, see glide_to()
+	glide: Option<Glide>,
+
+	// Optional PLL locking hardsync to a noisy external signal, see enable_sync_pll()/set_sync_signal()
+	sync_pll: Option<SyncPll>,
+
+	// Internal modulation LFOs, see add_lfo()/add_vibrato()
+	lfos: Vec<Lfo>,
+
 	// Const inited from environment
 	consts: SquineConfig
 }
@@ -40,7 +59,9 @@ struct SquineConfig {
 	sync_phase_inc: f64,
 	sync_trig: f64,  // = const 0.9997;
 	max_freq: f64,  // = 10000;  // Arbitrary limit
-	max_sweep_inc: f64  // = 1.0 / 5;
+	max_sweep_inc: f64,  // = 1.0 / 5;
+	#[cfg(feature = "fast_cos")]
+	fast_cos: bool
 }
 
 /// Returns maxval on over/underflow or NaN (rather than propagate the NaN)
@@ -65,6 +86,8 @@ impl SquineConfig {
 			sync_phase_inc: 1.0 / min_sweep.ln(),
 			sync_trig: 0.9997,    // If listening to a high-freq oscil, it should hit this value. Prefer a proper sync sig.
 			max_freq: 10000.0,    // Technical max is sample_rate / 2.0
+			#[cfg(feature = "fast_cos")]
+			fast_cos: false,    // Opt-in; off by default so accuracy-sensitive users are unaffected
 		}
 	} 
 }
@@ -92,9 +115,15 @@ impl Squinewave {
 			// Through-zero detection
 			raw_freq: 0.0,
 			neg_freq: false,
+			base_clip: 0.0,
+			base_skew: 1.0,
 			// Production
 			audio_out: 0.0,
 			sync_out: 0.0,
+			envelope: None,
+			glide: None,
+			sync_pll: None,
+			lfos: Vec::new(),
 		};
 		squine.set_init_phase(phase_in);
 		return squine;
@@ -122,10 +151,12 @@ impl Squinewave {
 	}
 	/// Clip: squareness of the waveform. Range 0.0 - 1.0
 	pub fn set_clip(&mut self, clip: f64) {
+		self.base_clip = clip;
 		self.clip = 1.0 - clamp(clip, 0.0, 1.0);
 	}
 	/// Skew: left-rigfht symmetry of waveform. Range -1.0 - +1.0.
 	pub fn set_skew(&mut self, skew: f64) {
+		self.base_skew = skew;
 		self.skew = 1.0 - clamp(skew, -1.0, 1.0);
 	}
 	/// Sync input. Set to 1.0 to start a fast sweep to restart waveform (around 0-20 samples), otherwise 0.0.  
@@ -134,6 +165,115 @@ impl Squinewave {
 		self.sync_in = sync >= self.consts.sync_trig;
 	}
 
+	/// Attaches a [SyncPll], an alternative to [set_sync()][Squinewave::set_sync] for locking hardsync to a
+	/// noisy external signal instead of a clean trigger. Feed it samples with
+	/// [set_sync_signal()][Squinewave::set_sync_signal]. `shift_freq`/`shift_phase` are the PLL's loop-gain/
+	/// bandwidth knobs; see [SyncPll::new].
+	pub fn enable_sync_pll(&mut self, shift_freq: u32, shift_phase: u32, dt2: u32) {
+		self.sync_pll = Some(SyncPll::new(shift_freq, shift_phase, dt2));
+	}
+
+	/// Feeds one sample of an external signal through the attached [SyncPll], triggering hardsync on each
+	/// edge it locks onto. No-op unless [enable_sync_pll()][Squinewave::enable_sync_pll] was called.
+	pub fn set_sync_signal(&mut self, sample: f64) {
+		if let Some(pll) = &mut self.sync_pll {
+			if pll.process(sample) {
+				self.sync_in = true;
+			}
+		}
+	}
+
+	/// Enable or disable the table-based fast-cosine approximation (requires the `fast_cos` cargo feature).
+	/// Off by default, since it trades a little accuracy for speed; existing accuracy-sensitive callers are unaffected.
+	#[cfg(feature = "fast_cos")]
+	pub fn set_fast_cos(&mut self, enable: bool) {
+		self.consts.fast_cos = enable;
+	}
+
+	/// Attaches an [Envelope] that [generate()][Squinewave::generate] will apply to [audio()][Squinewave::audio].
+	/// Replaces any envelope set previously. Pass `None` to go back to unenveloped output.
+	pub fn set_envelope(&mut self, envelope: Option<Envelope>) {
+		self.envelope = envelope;
+	}
+
+	/// Starts a new note: if an [Envelope] is attached, begins its attack stage (from its current level,
+	/// so retriggering mid-release/sustain doesn't click); otherwise (or if the envelope has already
+	/// finished) restarts the waveform at a clean zero-crossing (see
+	/// [set_init_phase()][Squinewave::set_init_phase]). Jumping phase while the envelope is still sounding
+	/// would itself click, per [set_init_phase()][Squinewave::set_init_phase]'s own warning.
+	pub fn note_on(&mut self) {
+		let envelope_finished = match &self.envelope {
+			Some(envelope) => envelope.is_finished(),
+			None => true,
+		};
+		if envelope_finished {
+			self.set_init_phase(-1.0);
+		}
+		if let Some(envelope) = &mut self.envelope {
+			envelope.note_on();
+		}
+	}
+
+	/// Releases the current note. No-op unless an [Envelope] is attached.
+	pub fn note_off(&mut self) {
+		if let Some(envelope) = &mut self.envelope {
+			envelope.note_off();
+		}
+	}
+
+	/// True once the attached [Envelope] has finished its release stage (always false without one attached),
+	/// so voice allocators know when it's safe to reap this instance.
+	pub fn is_finished(&self) -> bool {
+		match &self.envelope {
+			Some(envelope) => envelope.is_finished(),
+			None => false,
+		}
+	}
+
+	/// Ramps `freq` from its current value to `target_hz` over `duration_samples`, following `mode`.
+	/// [generate()][Squinewave::generate] advances the ramp once per sample; replaces any glide in progress.
+	/// `duration_samples` of 0 sets `freq` immediately.
+	///
+	/// The ramp itself always runs on magnitude (`target_hz.abs()`); the sign in effect when `glide_to`
+	/// is called (i.e. whether `freq` is currently inverted/through-zero) is held fixed for the whole
+	/// ramp, so the glide won't flip it as a side effect of interpolating toward a differently-signed
+	/// `target_hz`. While a glide is in progress, each [generate()][Squinewave::generate] overwrites freq,
+	/// so a [set_freq()][Squinewave::set_freq] call (including a sign change) only takes effect once the
+	/// glide has ended.
+	pub fn glide_to(&mut self, target_hz: f64, duration_samples: u64, mode: SweepMode) {
+		let sign = if self.raw_freq < 0.0 { -1.0 } else { 1.0 };
+		let target_freq = target_hz.abs();
+		if duration_samples == 0 {
+			self.glide = None;
+			self.set_freq(sign * target_freq);
+			return;
+		}
+		self.glide = Some(Glide {
+			start_freq: self.freq,
+			target_freq,
+			duration_samples,
+			elapsed: 0,
+			mode,
+			sign,
+		});
+	}
+
+	/// Attaches an internal LFO: evaluated once per [generate()][Squinewave::generate] and added to
+	/// `target`'s input before clamping. `rate_hz` is the LFO rate, `depth` its amount in `target`'s own
+	/// units (Hz for Freq, 0.0-1.0 for Clip, -1.0-+1.0 for Skew).
+	pub fn add_lfo(&mut self, shape: LfoShape, target: LfoTarget, rate_hz: f64, depth: f64) {
+		let sample_rate = 2.0 / self.consts.maxphase_by_sr;
+		self.lfos.push(Lfo::new(shape, target, rate_hz, LfoDepth::Absolute(depth), sample_rate));
+	}
+
+	/// Vibrato preset: a `rate_hz` sine LFO modulating `freq` by `depth_cents` (1/100 of a semitone),
+	/// recomputed each sample from the current freq so it tracks pitch changes (glide, new notes, ...).
+	/// Like a Sine/Freq [add_lfo()][Squinewave::add_lfo], except that the depth is in cents rather than Hz.
+	pub fn add_vibrato(&mut self, rate_hz: f64, depth_cents: f64) {
+		let sample_rate = 2.0 / self.consts.maxphase_by_sr;
+		self.lfos.push(Lfo::new(LfoShape::Sine, LfoTarget::Freq, rate_hz, LfoDepth::Cents(depth_cents), sample_rate));
+	}
+
 	/// Output value, available after [generate()][Squinewave::generate]
 	pub fn audio(&self) -> f64 { return self.audio_out; }
 	/// Sync output, updated by [generate()][Squinewave::generate]. Outputs 1.0 once per cycle, else 0.0
@@ -142,9 +282,12 @@ impl Squinewave {
 	/// # Audio production
 	/// Call once per sample. After this, [audio()][Squinewave::audio] and [sync()][Squinewave::sync] are updated.
 	pub fn generate(&mut self) {
+		self.advance_glide();
+		self.apply_lfos();
+
 		if self.sync_in {
 			self.hardsync_init();
-			self.sync_in = false;  // Reset here in case set_sync() is not called properly every sample 
+			self.sync_in = false;  // Reset here in case set_sync() is not called properly every sample
 		}
 
 		// hardsync ongoing? Increase freq until wraparound
@@ -181,7 +324,7 @@ impl Squinewave {
 		// Pure sine if freq > sr/(2*Min_Sweep)
 		if self.freq >= self.consts.max_sweep_freq {
 			// Continue from sweep_phase
-			self.audio_out = (PI * self.sweep_phase).cos();
+			self.audio_out = self.cos_pi(self.sweep_phase);
 			self.phase = self.sweep_phase;
 			self.sweep_phase += phase_inc;
 		}
@@ -193,7 +336,7 @@ impl Squinewave {
 			if self.sweep_phase < 1.0 {
 				let sweep_length = (self.clip * midpoint).max(min_sweep);
 
-				self.audio_out = (PI * self.sweep_phase).cos();
+				self.audio_out = self.cos_pi(self.sweep_phase);
 				self.sweep_phase += (phase_inc / sweep_length).min(self.consts.max_sweep_inc);
 
 				// Handle fractional sweep_phase overshoot after sweep ends
@@ -231,7 +374,7 @@ impl Squinewave {
 					// sweep_phase overshoot after flat part
 					self.sweep_phase = 1.0 + ( (self.phase - midpoint).min(phase_inc) / sweep_length ).min(self.consts.max_sweep_inc);
 				}
-				self.audio_out = (PI * self.sweep_phase).cos();
+				self.audio_out = self.cos_pi(self.sweep_phase);
 				self.sweep_phase += (phase_inc / sweep_length).min(self.consts.max_sweep_inc);
 
 				if self.sweep_phase > 2.0 {
@@ -288,9 +431,163 @@ impl Squinewave {
 			self.sync_out = 0.0;
 		}
 
+		if let Some(envelope) = &mut self.envelope {
+			self.audio_out *= envelope.advance();
+		}
+	}
+}
+
+
+/// Each call to [next()][Iterator::next] runs [generate()][Squinewave::generate] and returns the resulting
+/// [audio()][Squinewave::audio] sample. Useful for `squine.take(frames).collect()` or other iterator-based
+/// buffer building; freq/clip/skew stay at whatever they were last set to. Like generate(), each `next()`
+/// clears the sync input, so a sync set before iterating triggers hardsync on the first sample only.
+impl Iterator for Squinewave {
+	type Item = f64;
+
+	fn next(&mut self) -> Option<f64> {
+		self.generate();
+		Some(self.audio_out)
+	}
+}
+
+impl Squinewave {
+	/// Fills `out` with one [generate()][Squinewave::generate]d sample per element.
+	/// freq/clip/skew/sync are held constant across the block unless updated in between calls.
+	pub fn generate_block(&mut self, out: &mut [f64]) {
+		let sync_in = self.sync_in;
+		for sample in out.iter_mut() {
+			self.sync_in = sync_in;
+			self.generate();
+			*sample = self.audio_out;
+		}
+	}
+
+	/// Like [generate_block()][Squinewave::generate_block], additionally filling `sync` with the
+	/// per-sample [sync()][Squinewave::sync] output. `audio` and `sync` must be the same length.
+	pub fn generate_block_sync(&mut self, audio: &mut [f64], sync: &mut [f64]) {
+		debug_assert_eq!(audio.len(), sync.len(), "generate_block_sync: audio and sync must be the same length");
+		let sync_in = self.sync_in;
+		for (a, s) in audio.iter_mut().zip(sync.iter_mut()) {
+			self.sync_in = sync_in;
+			self.generate();
+			*a = self.audio_out;
+			*s = self.sync_out;
+		}
+	}
+}
+
+impl Squinewave {
+	/// `cos(PI * phase)`. Uses the table-based approximation from [fast_cos] in place of `f64::cos`
+	/// when fast-cos mode is enabled (see [set_fast_cos()][Squinewave::set_fast_cos]).
+	fn cos_pi(&self, phase: f64) -> f64 {
+		#[cfg(feature = "fast_cos")]
+		if self.consts.fast_cos {
+			// phase ranges 0-2, cos(PI * phase) == cos(2*PI * (phase/2))
+			return fast_cos::fast_cos(phase * 0.5);
+		}
+		(PI * phase).cos()
+	}
+}
+
+/// Table-based `cos(2*PI*x)` approximation, opt-in via the `fast_cos` cargo feature.
+#[cfg(feature = "fast_cos")]
+mod fast_cos {
+	use std::f64::consts::PI;
+	use std::sync::OnceLock;
+
+	const TAB_SIZE: usize = 512;
+
+	static COS_TAB: OnceLock<[f64; TAB_SIZE + 1]> = OnceLock::new();
+
+	fn init_cos_tab() -> [f64; TAB_SIZE + 1] {
+		let mut tab = [0.0; TAB_SIZE + 1];
+		for (i, entry) in tab.iter_mut().enumerate() {
+			*entry = (2.0 * PI * i as f64 / TAB_SIZE as f64).cos();
+		}
+		tab
+	}
+
+	/// Linearly-interpolated `cos(2*PI*frac)`. `frac` is normalized turns (any range; wraps).
+	/// The table's guard entry at `TAB_SIZE` (== entry 0) means interpolation never reads out of bounds.
+	pub(crate) fn fast_cos(frac: f64) -> f64 {
+		let tab = COS_TAB.get_or_init(init_cos_tab);
+		let idx_f = frac.rem_euclid(1.0) * TAB_SIZE as f64;
+		let i = (idx_f as usize).min(TAB_SIZE - 1);
+		let f = idx_f - i as f64;
+		tab[i] + f * (tab[i + 1] - tab[i])
+	}
+}
+
+impl Squinewave {
+	/// Advances an in-progress [glide_to()][Squinewave::glide_to] ramp by one sample, updating `freq`. No-op if idle.
+	fn advance_glide(&mut self) {
+		let Some(glide) = &mut self.glide else { return };
+
+		glide.elapsed += 1;
+		let finished = glide.elapsed >= glide.duration_samples;
+		let sign = glide.sign;
+		let freq = if finished {
+			glide.target_freq
+		}
+		else {
+			let t = glide.elapsed as f64 / glide.duration_samples as f64;
+			match glide.mode {
+				SweepMode::Linear => glide.start_freq + (glide.target_freq - glide.start_freq) * t,
+				SweepMode::Exponential => {
+					if glide.start_freq > 0.0 && glide.target_freq > 0.0 {
+						glide.start_freq * (glide.target_freq / glide.start_freq).powf(t)
+					}
+					else {
+						// Can't take a log-ratio through a zero endpoint; fall back to linear.
+						glide.start_freq + (glide.target_freq - glide.start_freq) * t
+					}
+				}
+				SweepMode::Square => glide.start_freq + (glide.target_freq - glide.start_freq) * (t * t),
+			}
+		};
+
+		if finished {
+			self.glide = None;
+		}
+		self.set_freq(sign * freq);
 	}
 }
 
+impl Squinewave {
+	/// Evaluates each attached [Lfo] for this sample and adds it to its target's input before clamping.
+	/// No-op if no LFOs are attached. Modulation is added around `raw_freq`/`base_clip`/`base_skew`
+	/// (rather than the previous sample's already-modulated value) so it doesn't drift over time.
+	fn apply_lfos(&mut self) {
+		if self.lfos.is_empty() {
+			return;
+		}
+
+		let base_freq = self.raw_freq.abs();
+		let mut freq_mod = 0.0;
+		let mut clip_mod = 0.0;
+		let mut skew_mod = 0.0;
+		for lfo in &mut self.lfos {
+			let unit = lfo.advance_unit();
+			let value = match lfo.depth {
+				LfoDepth::Absolute(depth) => unit * depth,
+				// Recomputed from the current carrier freq every sample, so vibrato depth tracks
+				// pitch changes (glide, new notes, ...) instead of freezing to whatever freq was
+				// active when the LFO was attached.
+				LfoDepth::Cents(cents) => unit * base_freq * (2f64.powf(cents / 1200.0) - 1.0),
+			};
+			match lfo.target {
+				LfoTarget::Freq => freq_mod += value,
+				LfoTarget::Clip => clip_mod += value,
+				LfoTarget::Skew => skew_mod += value,
+			}
+		}
+
+		self.freq = clamp(self.raw_freq.abs() + freq_mod, 0.0, self.consts.max_freq);
+		self.clip = 1.0 - clamp(self.base_clip + clip_mod, 0.0, 1.0);
+		self.skew = 1.0 - clamp(self.base_skew + skew_mod, -1.0, 1.0);
+	}
+}
 
 impl Squinewave {
 	fn hardsync_init(&mut self) {
@@ -374,3 +671,262 @@ impl Squinewave {
 		}
 	}
 }
+
+/// In-progress frequency ramp driven by [Squinewave::glide_to], advanced once per [Squinewave::generate].
+/// `start_freq`/`target_freq` are magnitudes; `sign` is frozen from the freq in effect when the glide
+/// started, so the ramp itself never flips through zero.
+struct Glide {
+	start_freq: f64,
+	target_freq: f64,
+	duration_samples: u64,
+	elapsed: u64,
+	mode: SweepMode,
+	sign: f64,
+}
+
+/// Curve used by [glide_to()][Squinewave::glide_to] to ramp from the start frequency to the target.
+#[derive(Clone, Copy)]
+pub enum SweepMode {
+	/// Interpolates `freq` uniformly.
+	Linear,
+	/// Interpolates in log-frequency, for equal musical steps per sample.
+	Exponential,
+	/// Eases in using `t*t`.
+	Square,
+}
+
+/// Waveform shape of an [Lfo], computed from the LFO's own 0-2 phase (the oscillator's phase convention;
+/// Sine is `cos(PI * phase)`). The oscillator's clip/skew shaping is not applied.
+#[derive(Clone, Copy)]
+pub enum LfoShape {
+	Sine,
+	Triangle,
+	Square,
+}
+
+/// Input an [Lfo] modulates, see [Squinewave::add_lfo].
+#[derive(Clone, Copy)]
+pub enum LfoTarget {
+	Freq,
+	Clip,
+	Skew,
+}
+
+/// How an [Lfo]'s amount is expressed.
+enum LfoDepth {
+	/// In `target`'s own units: Hz for Freq, 0.0-1.0 for Clip, -1.0-+1.0 for Skew.
+	Absolute(f64),
+	/// In cents (1/100 of a semitone) around the carrier's current freq. `Freq` target only; converted
+	/// to Hz fresh every sample so it tracks pitch changes.
+	Cents(f64),
+}
+
+/// Internal low-frequency modulator attached via [Squinewave::add_lfo] or [Squinewave::add_vibrato],
+/// evaluated once per [Squinewave::generate].
+struct Lfo {
+	target: LfoTarget,
+	shape: LfoShape,
+	depth: LfoDepth,
+	// phase range 0-2, matching Squinewave's own phase convention
+	phase: f64,
+	phase_inc: f64,
+}
+
+impl Lfo {
+	fn new(shape: LfoShape, target: LfoTarget, rate_hz: f64, depth: LfoDepth, sample_rate: f64) -> Self {
+		Lfo {
+			target,
+			shape,
+			depth,
+			phase: 0.0,
+			phase_inc: 2.0 * rate_hz / sample_rate,
+		}
+	}
+
+	/// Advances the LFO by one sample and returns its current waveform value, in -1.0..=1.0 (unscaled by depth).
+	fn advance_unit(&mut self) -> f64 {
+		let value = match self.shape {
+			LfoShape::Sine => (PI * self.phase).cos(),
+			LfoShape::Triangle => 1.0 - 2.0 * (self.phase - 1.0).abs(),
+			LfoShape::Square => if self.phase < 1.0 { 1.0 } else { -1.0 },
+		};
+
+		self.phase += self.phase_inc;
+		if self.phase >= 2.0 {
+			self.phase -= 2.0;
+		}
+
+		value
+	}
+}
+
+/// Reciprocal PLL that locks hardsync to the recovered fundamental period of a noisy external signal,
+/// attached via [Squinewave::enable_sync_pll] and fed via [Squinewave::set_sync_signal].
+///
+/// Tracks a frequency word `ff` (u32 fixed-point turns-per-sample) and phase accumulator `y`. Each
+/// detected rising zero-crossing nudges `ff` toward the measured period; every sample, the phase error
+/// between `y` and the free-running estimate folds into the locked frequency `f`, so the recovered edge
+/// timing stays stable even when individual crossings jitter.
+pub struct SyncPll {
+	ff: u32,
+	f: u32,
+	y: u32,
+	x: u32,
+	last_x: u32,
+	prev_sample: f64,
+
+	shift_freq: u32,
+	shift_phase: u32,
+	dt2: u32,
+}
+
+impl SyncPll {
+	/// - `shift_freq` / `shift_phase` - loop-gain / bandwidth knobs: higher values lock slower but reject
+	///   more jitter. Both are clamped to `dt2+1 ..= dt2+31`, the range the PLL's internal bit-shifts need
+	///   to stay valid (`shift_freq` appears as `shift_freq - 1` and `32 + dt2 - shift_freq`; `shift_phase`
+	///   appears as `shift_phase - dt2`; outside this range those would underflow or shift by >=32).
+	/// - `dt2` - log2 of PLL ticks per input sample; 0 if the PLL should tick once per sample.
+	pub fn new(shift_freq: u32, shift_phase: u32, dt2: u32) -> Self {
+		SyncPll {
+			ff: 0,
+			f: 0,
+			y: 0,
+			x: 0,
+			last_x: 0,
+			prev_sample: 0.0,
+			shift_freq: shift_freq.clamp(dt2 + 1, dt2 + 31),
+			shift_phase: shift_phase.clamp(dt2 + 1, dt2 + 31),
+			dt2,
+		}
+	}
+
+	/// Feeds one sample of the external signal through the PLL. The raw zero-crossing still drives the
+	/// frequency lock (below), but the retrigger itself is gated on the *locked* phase accumulator `y`
+	/// completing a full turn, not on the input's instantaneous (and possibly jittery/noisy) crossing.
+	/// Returns true on samples where that happens.
+	fn process(&mut self, sample: f64) -> bool {
+		let edge = self.prev_sample <= 0.0 && sample > 0.0;
+		self.prev_sample = sample;
+
+		self.x = self.x.wrapping_add(1 << self.dt2);
+
+		if edge {
+			let dx = self.x.wrapping_sub(self.last_x);
+			self.last_x = self.x;
+
+			let p_sig = ((self.ff as u64 * dx as u64 + (1u64 << (self.shift_freq - 1))) >> self.shift_freq) as u32;
+			let p_ref = 1u32 << (32 + self.dt2 - self.shift_freq);
+			self.ff = self.ff.wrapping_add(p_ref.wrapping_sub(p_sig));
+		}
+
+		// Phase error between the running estimate "now" and the accumulator, folded into the locked freq
+		let dt = self.x.wrapping_sub(self.last_x);
+		let y_ref = (self.f >> self.dt2).wrapping_mul(dt);
+		let dy = (y_ref.wrapping_sub(self.y) as i32) >> (self.shift_phase - self.dt2);
+		self.f = self.ff.wrapping_add(dy as u32);
+
+		// y wrapping past a full turn is the PLL's own recovered period elapsing - that's the retrigger,
+		// not the raw edge used above only to correct the lock.
+		let (y, wrapped) = self.y.overflowing_add(self.f);
+		self.y = y;
+
+		wrapped
+	}
+}
+
+/// Per-note amplitude envelope (attack/decay/sustain/release), owned by a [Squinewave] and driven
+/// through [Squinewave::note_on] / [Squinewave::note_off].
+///
+/// # Init values
+/// - `attack`, `decay`, `release` - stage times in seconds
+/// - `sustain` - sustain level, range 0.0 - 1.0
+/// - `sample_rate` of your application
+pub struct Envelope {
+	attack: f64,
+	decay: f64,
+	sustain: f64,
+	release: f64,
+	sample_rate: f64,
+
+	stage: EnvelopeStage,
+	level: f64,
+	release_start: f64,
+}
+
+enum EnvelopeStage {
+	Idle,
+	Attack,
+	Decay,
+	Sustain,
+	Release,
+}
+
+impl Envelope {
+	pub fn new(attack: f64, decay: f64, sustain: f64, release: f64, sample_rate: f64) -> Self {
+		Envelope {
+			attack: attack.max(0.0),
+			decay: decay.max(0.0),
+			sustain: clamp(sustain, 0.0, 1.0),
+			release: release.max(0.0),
+			sample_rate,
+			stage: EnvelopeStage::Idle,
+			level: 0.0,
+			release_start: 0.0,
+		}
+	}
+
+	/// (Re)starts the envelope from the attack stage.
+	fn note_on(&mut self) {
+		self.stage = EnvelopeStage::Attack;
+	}
+
+	/// Moves to the release stage, unless the envelope is already idle.
+	fn note_off(&mut self) {
+		if !matches!(self.stage, EnvelopeStage::Idle) {
+			self.release_start = self.level;
+			self.stage = EnvelopeStage::Release;
+		}
+	}
+
+	/// True once the release stage has fully decayed to silence (or the envelope was never started).
+	fn is_finished(&self) -> bool {
+		matches!(self.stage, EnvelopeStage::Idle)
+	}
+
+	/// Advances the envelope state machine by one sample and returns the current level, 0.0 - 1.0.
+	fn advance(&mut self) -> f64 {
+		match self.stage {
+			EnvelopeStage::Idle => {
+				self.level = 0.0;
+			}
+			EnvelopeStage::Attack => {
+				let inc = if self.attack > 0.0 { 1.0 / (self.attack * self.sample_rate) } else { 1.0 };
+				self.level += inc;
+				if self.level >= 1.0 {
+					self.level = 1.0;
+					self.stage = EnvelopeStage::Decay;
+				}
+			}
+			EnvelopeStage::Decay => {
+				let dec = if self.decay > 0.0 { (1.0 - self.sustain) / (self.decay * self.sample_rate) } else { 1.0 };
+				self.level -= dec;
+				if self.level <= self.sustain {
+					self.level = self.sustain;
+					self.stage = EnvelopeStage::Sustain;
+				}
+			}
+			EnvelopeStage::Sustain => {
+				self.level = self.sustain;
+			}
+			EnvelopeStage::Release => {
+				let dec = if self.release > 0.0 { self.release_start / (self.release * self.sample_rate) } else { self.release_start };
+				self.level -= dec;
+				if self.level <= 0.0 {
+					self.level = 0.0;
+					self.stage = EnvelopeStage::Idle;
+				}
+			}
+		}
+		self.level
+	}
+}